@@ -1,10 +1,13 @@
 mod querier;
 mod zone_iterator;
 
-use crate::querier::{IpFamilyMode, Querier};
+use crate::querier::{DsMatch, IpFamilyMode, Querier, Trust, TrustAnchor, IANA_ROOT_TRUST_ANCHOR};
 use clap::Parser;
 use console::style;
+use serde::Serialize;
+use std::fmt;
 use std::net::SocketAddr;
+use std::str::FromStr;
 use trust_dns_client::rr::Name;
 
 #[derive(Parser)]
@@ -18,16 +21,80 @@ struct Args {
     #[clap(long, short = 'f', default_value_t = querier::IpFamilyMode::Any)]
     ip_family_mode: IpFamilyMode,
 
+    #[clap(long, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    #[clap(long = "trust-anchor", default_value = IANA_ROOT_TRUST_ANCHOR)]
+    trust_anchors: Vec<TrustAnchor>,
+
     zone: Name,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err("could not parse output format".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            OutputFormat::Text => f.write_str("text"),
+            OutputFormat::Json => f.write_str("json"),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct StepResult {
+    parent: String,
+    child: String,
+    nameserver: Option<String>,
+    status: String,
+    reason: Option<String>,
+    ds_key_tag: Option<u16>,
+    ds_algorithm: Option<u8>,
+    ds_digest_type: Option<u8>,
+}
+
 fn main() {
     let args = Args::parse();
 
-    let querier = Querier::new(args.ip_family_mode);
+    let querier = Querier::new(args.ip_family_mode, args.root_address, args.origin.clone());
 
     let root_zone = querier.query_zone(args.origin.clone(), args.root_address);
 
+    let mut steps = Vec::new();
+
+    let trust_anchor_name = Name::from_str("trust-anchor").expect("valid pseudo name");
+    let root_trust = querier.verify_root(&root_zone, &args.trust_anchors);
+
+    if args.output == OutputFormat::Text {
+        match &root_trust {
+            Ok(trust) => print_trust(trust, trust_anchor_name.clone(), args.origin.clone()),
+            Err(message) => print_error(message.clone(), trust_anchor_name.clone(), args.origin.clone()),
+        }
+    }
+
+    steps.push(step_result(
+        &trust_anchor_name,
+        &args.origin,
+        Some(args.root_address),
+        &root_trust,
+    ));
+
     let mut last_zone = root_zone;
 
     for (parent, child) in zone_iterator::iter(args.zone.clone(), args.origin) {
@@ -39,35 +106,72 @@ fn main() {
 
             let result = querier.query_trust(&parent_zone, &last_zone);
 
-            match result {
-                Ok(trust) => print_trust(trust, parent, child),
-                Err(message) => print_error(message, parent, child),
+            if args.output == OutputFormat::Text {
+                match &result {
+                    Ok(trust) => print_trust(trust, parent.clone(), child.clone()),
+                    Err(message) => print_error(message.clone(), parent.clone(), child.clone()),
+                }
             }
+
+            steps.push(step_result(&parent, &child, Some(parent_addr), &result));
         } else {
-            print_error(
-                "no usable address found for nameserver".to_string(),
-                parent,
-                child,
-            );
+            let message = "no usable address found for nameserver".to_string();
+
+            if args.output == OutputFormat::Text {
+                print_error(message.clone(), parent.clone(), child.clone());
+            }
+
+            steps.push(step_result(&parent, &child, None, &Err(message)));
         }
     }
+
+    if args.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&steps).unwrap());
+    }
+}
+
+fn step_result(
+    parent: &Name,
+    child: &Name,
+    nameserver: Option<SocketAddr>,
+    result: &Result<Trust, String>,
+) -> StepResult {
+    let (status, reason, ds_match): (&str, Option<String>, Option<DsMatch>) = match result {
+        Ok(Trust::Trusted(ds_match)) => ("trusted", None, Some(ds_match.clone())),
+        Ok(Trust::Insecure) => ("insecure", None, None),
+        Ok(Trust::Untrusted(reason)) => ("untrusted", Some(reason.clone()), None),
+        Err(message) => ("error", Some(message.clone()), None),
+    };
+
+    StepResult {
+        parent: parent.to_string(),
+        child: child.to_string(),
+        nameserver: nameserver.map(|addr| addr.to_string()),
+        status: status.to_string(),
+        reason,
+        ds_key_tag: ds_match.as_ref().map(|m| m.key_tag),
+        ds_algorithm: ds_match.as_ref().map(|m| m.algorithm),
+        ds_digest_type: ds_match.as_ref().map(|m| m.digest_type),
+    }
 }
 
-fn print_trust(trust: querier::Trust, parent: Name, child: Name) {
+fn print_trust(trust: &querier::Trust, parent: Name, child: Name) {
     let styled_trust = match trust {
-        querier::Trust::Trusted => format!("{}", style("OK").green()),
+        querier::Trust::Trusted(_) => format!("{}", style("OK").green()),
+        querier::Trust::Insecure => format!("{}", style("Insecure").yellow()),
         querier::Trust::Untrusted(_) => format!("{}", style("Untrusted").red()),
     };
 
     let message = match trust {
-        querier::Trust::Untrusted(ref reason) => format!(" - {reason}"),
+        querier::Trust::Untrusted(reason) => format!(" - {reason}"),
         _ => "".to_string(),
     };
 
     let line = format!("[{styled_trust}] {parent} -> {child}{message}");
 
     match trust {
-        querier::Trust::Trusted => println!("{}", line),
+        querier::Trust::Trusted(_) => println!("{}", line),
+        querier::Trust::Insecure => println!("{}", line),
         querier::Trust::Untrusted(_) => eprintln!("{}", line),
     };
 }
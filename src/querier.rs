@@ -1,12 +1,20 @@
+use std::collections::HashSet;
 use std::fmt;
 use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use ring::digest::{Context, SHA1_FOR_LEGACY_USE_ONLY};
 use trust_dns_client::client::{Client, SyncClient};
-use trust_dns_client::op::DnsResponse;
-use trust_dns_client::rr::rdata::{DNSSECRData, DNSKEY, DS};
+use trust_dns_client::op::{DnsResponse, Edns, Message, MessageType, OpCode, Query};
+use trust_dns_client::rr::dnssec::DigestType;
+use trust_dns_client::rr::rdata::{DNSSECRData, DNSKEY, DS, SIG};
 use trust_dns_client::rr::{DNSClass, Name, RData, Record, RecordType};
+use trust_dns_client::serialize::binary::{BinEncodable, BinEncoder};
+use trust_dns_client::tcp::TcpClientConnection;
 use trust_dns_client::udp::UdpClientConnection;
 
+use crate::zone_iterator;
+
 #[derive(Debug, Clone)]
 pub struct Nameserver {
     name: Name,
@@ -79,6 +87,290 @@ fn to_ns(response: DnsResponse) -> Nameservers {
     name_servers
 }
 
+fn dnskey_rrset_tbs(owner: &Name, sig: &SIG, dnskey_records: &[DNSKEY]) -> Vec<u8> {
+    let mut tbs = Vec::new();
+
+    {
+        let mut encoder = BinEncoder::new(&mut tbs);
+        sig.type_covered().emit(&mut encoder).unwrap();
+        encoder.emit(sig.algorithm().into()).unwrap();
+        encoder.emit(sig.num_labels()).unwrap();
+        encoder.emit_u32(sig.original_ttl()).unwrap();
+        encoder.emit_u32(sig.sig_expiration()).unwrap();
+        encoder.emit_u32(sig.sig_inception()).unwrap();
+        encoder.emit_u16(sig.key_tag()).unwrap();
+        sig.signer_name().emit_as_canonical(&mut encoder, true).unwrap();
+    }
+
+    let mut sorted = dnskey_records.to_vec();
+    sorted.sort_by_cached_key(canonical_rdata);
+
+    for dnskey in &sorted {
+        let rdata = canonical_rdata(dnskey);
+
+        // Each RR is encoded into its own buffer and appended, since a fresh
+        // BinEncoder always starts writing at offset 0 and would otherwise
+        // overwrite the bytes already placed in `tbs`.
+        let mut rr = Vec::new();
+        let mut encoder = BinEncoder::new(&mut rr);
+        owner.emit_as_canonical(&mut encoder, true).unwrap();
+        RecordType::DNSKEY.emit(&mut encoder).unwrap();
+        DNSClass::IN.emit(&mut encoder).unwrap();
+        encoder.emit_u32(sig.original_ttl()).unwrap();
+        encoder.emit_u16(rdata.len() as u16).unwrap();
+        encoder.emit_vec(&rdata).unwrap();
+        drop(encoder);
+
+        tbs.extend(rr);
+    }
+
+    tbs
+}
+
+fn canonical_rdata(dnskey: &DNSKEY) -> Vec<u8> {
+    let mut rdata = Vec::new();
+    let mut encoder = BinEncoder::new(&mut rdata);
+    dnskey.emit(&mut encoder).unwrap();
+    rdata
+}
+
+// Builds the RFC 4034 section 3.1.8.1 canonical "to be signed" form for an
+// arbitrary authenticated RRset, given its raw records straight from a
+// response's authority section. This mirrors dnskey_rrset_tbs, but works
+// against generic Records rather than a pre-extracted Vec<DNSKEY>, since the
+// NSEC/NSEC3 RRsets verified here are not split out by query_ds the way
+// query_dnskey splits out DNSKEY records.
+fn rrset_tbs(owner: &Name, record_type: RecordType, sig: &SIG, records: &[Record]) -> Vec<u8> {
+    let mut tbs = Vec::new();
+
+    {
+        let mut encoder = BinEncoder::new(&mut tbs);
+        sig.type_covered().emit(&mut encoder).unwrap();
+        encoder.emit(sig.algorithm().into()).unwrap();
+        encoder.emit(sig.num_labels()).unwrap();
+        encoder.emit_u32(sig.original_ttl()).unwrap();
+        encoder.emit_u32(sig.sig_expiration()).unwrap();
+        encoder.emit_u32(sig.sig_inception()).unwrap();
+        encoder.emit_u16(sig.key_tag()).unwrap();
+        sig.signer_name().emit_as_canonical(&mut encoder, true).unwrap();
+    }
+
+    let mut rdatas = records
+        .iter()
+        .filter_map(|record| record.data())
+        .map(|data| {
+            let mut buf = Vec::new();
+            let mut encoder = BinEncoder::new(&mut buf);
+            data.emit(&mut encoder).unwrap();
+            buf
+        })
+        .collect::<Vec<Vec<u8>>>();
+    rdatas.sort();
+
+    for rdata in &rdatas {
+        let mut rr = Vec::new();
+        let mut encoder = BinEncoder::new(&mut rr);
+        owner.emit_as_canonical(&mut encoder, true).unwrap();
+        record_type.emit(&mut encoder).unwrap();
+        DNSClass::IN.emit(&mut encoder).unwrap();
+        encoder.emit_u32(sig.original_ttl()).unwrap();
+        encoder.emit_u16(rdata.len() as u16).unwrap();
+        encoder.emit_vec(rdata).unwrap();
+        drop(encoder);
+
+        tbs.extend(rr);
+    }
+
+    tbs
+}
+
+// Returns the RRset of `record_type` at `owner` found in `authority`, but
+// only if it is covered by an RRSIG that validates against one of
+// `dnskey_records`. Returns an empty Vec (not an error) when no such RRset
+// is present at all, so callers can fall through to check other proofs.
+fn verify_authenticated_rrset<'a>(
+    owner: &Name,
+    record_type: RecordType,
+    authority: &'a [Record],
+    dnskey_records: &[DNSKEY],
+) -> Result<Vec<&'a Record>, String> {
+    let rrset = authority
+        .iter()
+        .filter(|record| record.name() == owner && record.record_type() == record_type)
+        .collect::<Vec<&Record>>();
+
+    if rrset.is_empty() {
+        return Ok(rrset);
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as u32;
+
+    let owned_rrset = rrset.iter().map(|record| (*record).clone()).collect::<Vec<Record>>();
+
+    let sigs = authority
+        .iter()
+        .filter(|record| record.name() == owner)
+        .filter_map(|record| record.data().and_then(RData::as_dnssec).and_then(DNSSECRData::as_sig))
+        .filter(|sig| sig.type_covered() == record_type);
+
+    for sig in sigs {
+        if now < sig.sig_inception() || now > sig.sig_expiration() {
+            continue;
+        }
+
+        let signer = dnskey_records
+            .iter()
+            .find(|dnskey| dnskey.calculate_key_tag().unwrap() == sig.key_tag() && dnskey.algorithm() == sig.algorithm());
+
+        let signer = match signer {
+            Some(signer) => signer,
+            None => continue,
+        };
+
+        let public_key = match signer.to_public_key() {
+            Ok(public_key) => public_key,
+            Err(_) => continue,
+        };
+
+        let tbs = rrset_tbs(owner, record_type, sig, &owned_rrset);
+
+        if public_key.verify(sig.algorithm(), &tbs, sig.sig()).is_ok() {
+            return Ok(rrset);
+        }
+    }
+
+    Err(format!("no valid RRSIG over the {record_type:?} RRset at {owner}"))
+}
+
+fn verify_insecure_delegation(
+    child: &Name,
+    authority: &[Record],
+    parent_dnskey_records: &[DNSKEY],
+) -> Result<(), String> {
+    let nsec_rrset = verify_authenticated_rrset(child, RecordType::NSEC, authority, parent_dnskey_records)?;
+
+    if let Some(record) = nsec_rrset.first() {
+        let nsec = record
+            .data()
+            .and_then(RData::as_dnssec)
+            .and_then(DNSSECRData::as_nsec)
+            .unwrap();
+
+        return if nsec.type_bit_maps().contains(&RecordType::NS) && !nsec.type_bit_maps().contains(&RecordType::DS) {
+            Ok(())
+        } else {
+            Err("authenticated NSEC at the delegation name does not prove an insecure delegation".to_string())
+        };
+    }
+
+    let first_nsec3 = authority
+        .iter()
+        .find_map(|record| record.data().and_then(RData::as_dnssec).and_then(DNSSECRData::as_nsec3));
+
+    let params = match first_nsec3 {
+        Some(nsec3) => nsec3,
+        None => return Err("missing DS, and no NSEC/NSEC3 proof present".to_string()),
+    };
+
+    // zone_iterator only ever walks one label at a time, so `child` is always
+    // an immediate child of the zone we just queried, and that zone's apex is
+    // trivially a provable closest encloser. The next closer name is
+    // therefore `child` itself.
+    let next_closer_hash = nsec3_hash(child, params.iterations(), params.salt());
+
+    let nsec3_owners = authority
+        .iter()
+        .filter(|record| record.record_type() == RecordType::NSEC3)
+        .map(|record| record.name().clone())
+        .collect::<Vec<Name>>();
+
+    for owner in &nsec3_owners {
+        let nsec3_rrset = match verify_authenticated_rrset(owner, RecordType::NSEC3, authority, parent_dnskey_records) {
+            Ok(nsec3_rrset) => nsec3_rrset,
+            Err(_) => continue,
+        };
+
+        let record = match nsec3_rrset.first() {
+            Some(record) => record,
+            None => continue,
+        };
+
+        let nsec3 = record
+            .data()
+            .and_then(RData::as_dnssec)
+            .and_then(DNSSECRData::as_nsec3)
+            .unwrap();
+
+        let owner_hash = match owner.iter().next().and_then(base32hex_decode) {
+            Some(owner_hash) => owner_hash,
+            None => continue,
+        };
+
+        if owner_hash == next_closer_hash {
+            return if nsec3.type_bit_maps().contains(&RecordType::NS) && !nsec3.type_bit_maps().contains(&RecordType::DS) {
+                Ok(())
+            } else {
+                Err("authenticated NSEC3 at the delegation name does not prove an insecure delegation".to_string())
+            };
+        }
+
+        let next_hash = nsec3.next_hashed_owner_name().to_vec();
+        let covers = if next_hash <= owner_hash {
+            next_closer_hash > owner_hash || next_closer_hash < next_hash
+        } else {
+            next_closer_hash > owner_hash && next_closer_hash < next_hash
+        };
+
+        if covers && nsec3.opt_out() {
+            return Ok(());
+        }
+    }
+
+    Err("missing DS, and no authenticated NSEC/NSEC3 proof of an insecure delegation".to_string())
+}
+
+fn nsec3_hash(name: &Name, iterations: u16, salt: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    {
+        let mut encoder = BinEncoder::new(&mut buf);
+        name.emit_as_canonical(&mut encoder, true).unwrap();
+    }
+
+    let mut digest = buf;
+    for _ in 0..=iterations {
+        let mut ctx = Context::new(&SHA1_FOR_LEGACY_USE_ONLY);
+        ctx.update(&digest);
+        ctx.update(salt);
+        digest = ctx.finish().as_ref().to_vec();
+    }
+
+    digest
+}
+
+fn base32hex_decode(label: &[u8]) -> Option<Vec<u8>> {
+    const ALPHABET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+    let mut out = Vec::new();
+
+    for &byte in label {
+        let index = ALPHABET.iter().position(|&c| c == byte.to_ascii_uppercase())? as u64;
+        buffer = (buffer << 5) | index;
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            out.push(((buffer >> bits) & 0xff) as u8);
+        }
+    }
+
+    Some(out)
+}
+
 fn into_address(record: &Record) -> Option<IpAddr> {
     let data = record.data()?;
     match record.record_type() {
@@ -88,11 +380,100 @@ fn into_address(record: &Record) -> Option<IpAddr> {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct DsMatch {
+    pub key_tag: u16,
+    pub algorithm: u8,
+    pub digest_type: u8,
+}
+
+#[derive(Clone)]
 pub enum Trust {
-    Trusted,
+    Trusted(DsMatch),
+    Insecure,
     Untrusted(String),
 }
 
+pub const IANA_ROOT_TRUST_ANCHOR: &str =
+    "20326 8 2 E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8A";
+
+#[derive(Clone, Debug)]
+pub struct TrustAnchor {
+    key_tag: u16,
+    algorithm: u8,
+    digest_type: u8,
+    digest: Vec<u8>,
+}
+
+impl FromStr for TrustAnchor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+
+        let key_tag = parts
+            .next()
+            .ok_or("missing key tag")?
+            .parse::<u16>()
+            .map_err(|e| e.to_string())?;
+
+        let algorithm = parts
+            .next()
+            .ok_or("missing algorithm")?
+            .parse::<u8>()
+            .map_err(|e| e.to_string())?;
+
+        let digest_type = parts
+            .next()
+            .ok_or("missing digest type")?
+            .parse::<u8>()
+            .map_err(|e| e.to_string())?;
+
+        let digest_hex = parts.next().ok_or("missing digest")?;
+
+        if digest_hex.len() % 2 != 0 {
+            return Err("digest must have an even number of hex digits".to_string());
+        }
+
+        let digest = (0..digest_hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digest_hex[i..i + 2], 16).map_err(|e| e.to_string()))
+            .collect::<Result<Vec<u8>, String>>()?;
+
+        Ok(TrustAnchor {
+            key_tag,
+            algorithm,
+            digest_type,
+            digest,
+        })
+    }
+}
+
+impl fmt::Display for TrustAnchor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let digest = self
+            .digest
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<String>();
+
+        write!(
+            f,
+            "{} {} {} {}",
+            self.key_tag, self.algorithm, self.digest_type, digest
+        )
+    }
+}
+
+fn digest_type_from_u8(value: u8) -> Option<DigestType> {
+    match value {
+        1 => Some(DigestType::SHA1),
+        2 => Some(DigestType::SHA256),
+        4 => Some(DigestType::SHA384),
+        _ => None,
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum IpFamilyMode {
     Any,
@@ -125,49 +506,260 @@ impl fmt::Display for IpFamilyMode {
 
 pub struct Querier {
     af_mode: IpFamilyMode,
+    root_address: SocketAddr,
+    origin: Name,
 }
 
 impl Querier {
-    pub fn new(af_mode: IpFamilyMode) -> Querier {
-        Querier { af_mode }
+    pub fn new(af_mode: IpFamilyMode, root_address: SocketAddr, origin: Name) -> Querier {
+        Querier {
+            af_mode,
+            root_address,
+            origin,
+        }
     }
 
     pub fn query_zone(&self, name: Name, address: SocketAddr) -> Zone {
-        let conn = UdpClientConnection::new(address).unwrap();
-        let client = SyncClient::new(conn);
-        let ns: DnsResponse = client.query(&name, DNSClass::IN, RecordType::NS).unwrap();
+        self.query_zone_with_visited(name, address, &mut HashSet::new())
+    }
+
+    fn query_zone_with_visited(
+        &self,
+        name: Name,
+        address: SocketAddr,
+        visited: &mut HashSet<Name>,
+    ) -> Zone {
+        let ns = self
+            .query(&name, RecordType::NS, address)
+            .expect("failed to query nameserver");
 
         Zone {
             name,
-            nameservers: to_ns(ns),
+            nameservers: self.resolve_glue(to_ns(ns), visited),
+        }
+    }
+
+    fn resolve_glue(&self, nameservers: Nameservers, visited: &mut HashSet<Name>) -> Nameservers {
+        nameservers
+            .into_iter()
+            .map(|ns| {
+                if !ns.addresses.is_empty() {
+                    return ns;
+                }
+
+                if !visited.insert(ns.name.clone()) {
+                    return ns;
+                }
+
+                let addresses = self.resolve_nameserver_addresses(&ns.name, visited);
+                Nameserver { addresses, ..ns }
+            })
+            .collect()
+    }
+
+    fn resolve_nameserver_addresses(&self, name: &Name, visited: &mut HashSet<Name>) -> Vec<IpAddr> {
+        let mut zone = self.query_zone_with_visited(self.origin.clone(), self.root_address, visited);
+
+        for (_, child) in zone_iterator::iter(name.clone(), self.origin.clone()) {
+            let addr = match random_address(zone.nameservers(), self.af_mode) {
+                Some(addr) => addr,
+                None => break,
+            };
+
+            let next_zone = self.query_zone_with_visited(child, SocketAddr::new(addr, 53), visited);
+
+            if next_zone.nameservers().is_empty() {
+                break;
+            }
+
+            zone = next_zone;
+        }
+
+        let addr = match random_address(zone.nameservers(), self.af_mode) {
+            Some(addr) => addr,
+            None => return vec![],
+        };
+
+        let sock = SocketAddr::new(addr, 53);
+        let record_types: &[RecordType] = match self.af_mode {
+            IpFamilyMode::Ipv4 => &[RecordType::A],
+            IpFamilyMode::Ipv6 => &[RecordType::AAAA],
+            IpFamilyMode::Any => &[RecordType::A, RecordType::AAAA],
+        };
+
+        record_types
+            .iter()
+            .filter_map(|record_type| self.query(name, *record_type, sock).ok())
+            .flat_map(|response| response.answers().to_vec())
+            .filter_map(|record| into_address(&record))
+            .collect()
+    }
+
+    pub fn verify_root(&self, root: &Zone, anchors: &[TrustAnchor]) -> Result<Trust, String> {
+        let (dnskey_records, rrsigs) = self.query_dnskey(root)?;
+
+        let mut reason = "no DNSKEY matched any trust anchor".to_string();
+        let mut ds_match = None;
+
+        for anchor in anchors {
+            for dnskey in dnskey_records.iter() {
+                if anchor.key_tag != dnskey.calculate_key_tag().unwrap() {
+                    continue;
+                }
+
+                if anchor.algorithm != u8::from(dnskey.algorithm()) {
+                    reason = "trust anchor algorithm does not match DNSKEY algorithm".to_string();
+                    continue;
+                }
+
+                let digest_type = match digest_type_from_u8(anchor.digest_type) {
+                    Some(digest_type) => digest_type,
+                    None => {
+                        reason = "unsupported trust anchor digest type".to_string();
+                        continue;
+                    }
+                };
+
+                let digest = dnskey
+                    .to_digest(&root.name, digest_type)
+                    .map_err(|e| e.to_string())?;
+
+                if digest.as_ref() == anchor.digest.as_slice() {
+                    ds_match = Some(DsMatch {
+                        key_tag: anchor.key_tag,
+                        algorithm: anchor.algorithm,
+                        digest_type: anchor.digest_type,
+                    });
+                    break;
+                }
+
+                reason = "trust anchor digest does not match DNSKEY".to_string();
+            }
+
+            if ds_match.is_some() {
+                break;
+            }
+        }
+
+        let ds_match = match ds_match {
+            Some(ds_match) => ds_match,
+            None => return Ok(Trust::Untrusted(reason)),
+        };
+
+        // Relies on dnskey_rrset_tbs producing the correct RFC 4034 canonical
+        // form; a genuine RRSIG over the root DNSKEY RRset must validate here.
+        match Self::verify_dnskey_rrset(root, &dnskey_records, &rrsigs) {
+            Ok(()) => Ok(Trust::Trusted(ds_match)),
+            Err(reason) => Ok(Trust::Untrusted(reason)),
         }
     }
 
     pub fn query_trust(&self, parent: &Zone, child: &Zone) -> Result<Trust, String> {
-        let ds_records = self.query_ds(parent, child.name.clone())?;
-        let dnskey_records = self.query_dnskey(child)?;
+        let (ds_records, authority) = self.query_ds(parent, child.name.clone())?;
+        let (dnskey_records, rrsigs) = self.query_dnskey(child)?;
+
+        if ds_records.is_empty() {
+            let (parent_dnskey_records, _) = self.query_dnskey(parent)?;
+
+            return match verify_insecure_delegation(&child.name, &authority, &parent_dnskey_records) {
+                Ok(()) => Ok(Trust::Insecure),
+                Err(reason) => Ok(Trust::Untrusted(reason)),
+            };
+        }
 
-        for ds in ds_records {
+        let mut reason = "no DNSKEY matched any DS record".to_string();
+        let mut ds_match = None;
+
+        for ds in &ds_records {
             for dnskey in dnskey_records.iter() {
-                if ds.key_tag() == dnskey.calculate_key_tag().unwrap()
-                    && ds.algorithm() == dnskey.algorithm()
-                {
-                    return Ok(Trust::Trusted);
+                if ds.key_tag() != dnskey.calculate_key_tag().unwrap() {
+                    continue;
+                }
+
+                if ds.algorithm() != dnskey.algorithm() {
+                    reason = "DS algorithm does not match DNSKEY algorithm".to_string();
+                    continue;
+                }
+
+                let digest = dnskey
+                    .to_digest(&child.name, ds.digest_type())
+                    .map_err(|e| e.to_string())?;
+
+                if digest.as_ref() == ds.digest() {
+                    ds_match = Some(DsMatch {
+                        key_tag: ds.key_tag(),
+                        algorithm: ds.algorithm().into(),
+                        digest_type: ds.digest_type().into(),
+                    });
+                    break;
+                }
+
+                reason = "DS digest does not match DNSKEY".to_string();
+            }
+
+            if ds_match.is_some() {
+                break;
+            }
+        }
+
+        let ds_match = match ds_match {
+            Some(ds_match) => ds_match,
+            None => return Ok(Trust::Untrusted(reason)),
+        };
+
+        match Self::verify_dnskey_rrset(child, &dnskey_records, &rrsigs) {
+            Ok(()) => Ok(Trust::Trusted(ds_match)),
+            Err(reason) => Ok(Trust::Untrusted(reason)),
+        }
+    }
+
+    fn verify_dnskey_rrset(
+        zone: &Zone,
+        dnskey_records: &[DNSKEY],
+        rrsigs: &[SIG],
+    ) -> Result<(), String> {
+        let ksk = dnskey_records
+            .iter()
+            .find(|dnskey| dnskey.secure_entry_point())
+            .ok_or_else(|| "no key signing key found in DNSKEY RRset".to_string())?;
+
+        let key_tag = ksk.calculate_key_tag().map_err(|e| e.to_string())?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as u32;
+
+        let mut reason = "no RRSIG found for the key signing key".to_string();
+
+        for sig in rrsigs
+            .iter()
+            .filter(|sig| sig.key_tag() == key_tag && sig.algorithm() == ksk.algorithm())
+        {
+            if now < sig.sig_inception() || now > sig.sig_expiration() {
+                reason = "RRSIG over DNSKEY RRset is outside its validity period".to_string();
+                continue;
+            }
+
+            let tbs = dnskey_rrset_tbs(&zone.name, sig, dnskey_records);
+            let public_key = ksk.to_public_key().map_err(|e| e.to_string())?;
+
+            match public_key.verify(sig.algorithm(), &tbs, sig.sig()) {
+                Ok(()) => return Ok(()),
+                Err(_) => {
+                    reason = "RRSIG over DNSKEY RRset did not validate against the KSK".to_string()
                 }
             }
         }
 
-        Ok(Trust::Untrusted("missing DS".to_string()))
+        Err(reason)
     }
 
-    fn query_ds(&self, parent: &Zone, child: Name) -> Result<Vec<DS>, String> {
+    fn query_ds(&self, parent: &Zone, child: Name) -> Result<(Vec<DS>, Vec<Record>), String> {
         if let Some(parent_addr) = random_address(parent.nameservers(), self.af_mode) {
             let sock = SocketAddr::new(parent_addr, 53);
-            let conn = UdpClientConnection::new(sock).unwrap();
-            let client = SyncClient::new(conn);
-            let ds: DnsResponse = client.query(&child, DNSClass::IN, RecordType::DS).unwrap();
+            let ds = self.query(&child, RecordType::DS, sock)?;
 
-            return Ok(ds
+            let ds_records = ds
                 .answers()
                 .iter()
                 .map(|x| {
@@ -177,34 +769,118 @@ impl Querier {
                         .unwrap()
                         .clone()
                 })
-                .collect::<Vec<DS>>());
+                .collect::<Vec<DS>>();
+
+            return Ok((ds_records, ds.name_servers().to_vec()));
         }
 
         Err("no name server address found".to_string())
     }
 
-    fn query_dnskey(&self, child: &Zone) -> Result<Vec<DNSKEY>, String> {
+    fn query_dnskey(&self, child: &Zone) -> Result<(Vec<DNSKEY>, Vec<SIG>), String> {
         if let Some(child_addr) = random_address(child.nameservers(), self.af_mode) {
             let sock = SocketAddr::new(child_addr, 53);
-            let conn = UdpClientConnection::new(sock).unwrap();
-            let client = SyncClient::new(conn);
-            let dnskey: DnsResponse = client
-                .query(&child.name, DNSClass::IN, RecordType::DNSKEY)
-                .unwrap();
+            let dnskey = self.query(&child.name, RecordType::DNSKEY, sock)?;
 
-            return Ok(dnskey
+            let dnskey_records = dnskey
                 .answers()
                 .iter()
-                .map(|x| {
+                .filter_map(|x| {
                     x.data()
                         .and_then(RData::as_dnssec)
                         .and_then(DNSSECRData::as_dnskey)
-                        .unwrap()
-                        .clone()
                 })
-                .collect::<Vec<DNSKEY>>());
+                .cloned()
+                .collect::<Vec<DNSKEY>>();
+
+            let rrsigs = dnskey
+                .answers()
+                .iter()
+                .filter_map(|x| x.data().and_then(RData::as_dnssec).and_then(DNSSECRData::as_sig))
+                .filter(|sig| sig.type_covered() == RecordType::DNSKEY)
+                .cloned()
+                .collect::<Vec<SIG>>();
+
+            return Ok((dnskey_records, rrsigs));
         }
 
         Err("no name server address found".to_string())
     }
+
+    fn query(
+        &self,
+        name: &Name,
+        record_type: RecordType,
+        address: SocketAddr,
+    ) -> Result<DnsResponse, String> {
+        let message = build_message(name, record_type);
+
+        let udp_conn = UdpClientConnection::new(address).map_err(|e| e.to_string())?;
+        let mut udp_client = SyncClient::new(udp_conn);
+        let response = udp_client.send(message.clone()).map_err(|e| e.to_string())?;
+
+        if !response.header().truncated() {
+            return Ok(response);
+        }
+
+        let tcp_conn = TcpClientConnection::new(address).map_err(|e| e.to_string())?;
+        let mut tcp_client = SyncClient::new(tcp_conn);
+        tcp_client.send(message).map_err(|e| e.to_string())
+    }
+}
+
+fn build_message(name: &Name, record_type: RecordType) -> Message {
+    let mut message = Message::new();
+    message
+        .set_id(rand_id())
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(true)
+        .add_query(Query::query(name.clone(), record_type));
+
+    let mut edns = Edns::new();
+    edns.set_dnssec_ok(true);
+    edns.set_max_payload(4096);
+    message.set_edns(edns);
+
+    message
+}
+
+fn rand_id() -> u16 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .subsec_nanos() as u16
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    // RFC 5155 appendix B: hash of "example." with salt aabbccdd, 12 iterations.
+    #[test]
+    fn test_nsec3_hash_rfc5155_example() {
+        let name = Name::from_str("example.").unwrap();
+        let salt = [0xaa, 0xbb, 0xcc, 0xdd];
+
+        let hash = nsec3_hash(&name, 12, &salt);
+        let expected = base32hex_decode(b"0p9mhaveqvm6t7vbl5lop2u3t2rp3tom").unwrap();
+
+        assert_eq!(hash, expected);
+    }
+
+    #[test]
+    fn test_base32hex_decode_is_case_insensitive() {
+        let lower = base32hex_decode(b"0p9mhaveqvm6t7vbl5lop2u3t2rp3tom").unwrap();
+        let upper = base32hex_decode(b"0P9MHAVEQVM6T7VBL5LOP2U3T2RP3TOM").unwrap();
+
+        assert_eq!(lower, upper);
+        assert_eq!(lower.len(), 20);
+    }
+
+    #[test]
+    fn test_base32hex_decode_rejects_invalid_characters() {
+        assert!(base32hex_decode(b"not-base32hex!").is_none());
+    }
 }